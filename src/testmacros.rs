@@ -3,80 +3,139 @@ macro_rules! effects {
     (addr_deposit, $amount:expr) => {
         (
             Transaction::address_deposit($amount),
-            Effects {
+            SettleOutcome::Applied(Effects {
                 address_delta: BalanceDelta($amount, 0),
                 object_delta: BalanceDelta(0, 0),
-            },
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
     (addr_withdraw, $amount:expr) => {
         (
             Transaction::address_withdraw($amount),
-            Effects {
-                address_delta: BalanceDelta(-$amount, 0),
+            SettleOutcome::Applied(Effects {
+                address_delta: BalanceDelta::debit($amount),
                 object_delta: BalanceDelta(0, 0),
-            },
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
-    (obj_deposit, $amount:expr) => {
+    (addr_cursed_deposit, $amount:expr) => {
         (
-            Transaction::object_deposit($amount),
-            Effects {
+            Transaction::address_cursed_deposit($amount),
+            SettleOutcome::Applied(Effects {
+                address_delta: BalanceDelta($amount, $amount),
+                object_delta: BalanceDelta(0, 0),
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
+        )
+    };
+
+    (obj_cursed_deposit, $amount:expr) => {
+        (
+            Transaction::object_cursed_deposit($amount),
+            SettleOutcome::Applied(Effects {
                 address_delta: BalanceDelta(0, 0),
-                object_delta: BalanceDelta($amount, 0),
-            },
+                object_delta: BalanceDelta($amount, $amount),
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
-    (obj_withdraw, $amount:expr, $actual:expr) => {
+    (obj_deposit, $amount:expr) => {
         (
-            Transaction::object_withdraw($amount),
-            Effects {
+            Transaction::object_deposit($amount),
+            SettleOutcome::Applied(Effects {
                 address_delta: BalanceDelta(0, 0),
-                object_delta: BalanceDelta(-$actual, 0),
-            },
+                object_delta: BalanceDelta($amount, 0),
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
+    (obj_withdraw, $amount:expr, $actual:expr) => {{
+        let requested = $amount;
+        let applied = $actual;
+        let effects = Effects {
+            address_delta: BalanceDelta(0, 0),
+            object_delta: BalanceDelta::debit(applied),
+            curse_breakdown: None,
+            clamped_from: None,
+            reference: None,
+        };
+        let outcome = if applied < requested {
+            SettleOutcome::PartiallyApplied {
+                requested,
+                applied,
+                effects,
+            }
+        } else {
+            SettleOutcome::Applied(effects)
+        };
+        (Transaction::object_withdraw(requested), outcome)
+    }};
+
     (obj_curse, $amount:expr) => {
         (
             Transaction::object_curse($amount),
-            Effects {
+            SettleOutcome::Applied(Effects {
                 address_delta: BalanceDelta(0, 0),
                 object_delta: BalanceDelta(0, $amount),
-            },
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
     (obj_clawback, $amount:expr) => {
         (
             Transaction::object_clawback($amount),
-            Effects {
+            SettleOutcome::Applied(Effects {
                 address_delta: BalanceDelta(0, 0),
-                object_delta: BalanceDelta(-$amount, -$amount),
-            },
+                object_delta: BalanceDelta::double_debit($amount),
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
     (addr_curse, $amount:expr) => {
         (
             Transaction::address_curse($amount),
-            Effects {
+            SettleOutcome::Applied(Effects {
                 address_delta: BalanceDelta(0, $amount),
                 object_delta: BalanceDelta(0, 0),
-            },
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 
     (addr_clawback, $amount:expr) => {
         (
             Transaction::address_clawback($amount),
-            Effects {
-                address_delta: BalanceDelta(-$amount, -$amount),
+            SettleOutcome::Applied(Effects {
+                address_delta: BalanceDelta::double_debit($amount),
                 object_delta: BalanceDelta(0, 0),
-            },
+                curse_breakdown: None,
+                clamped_from: None,
+                reference: None,
+            }),
         )
     };
 }